@@ -8,6 +8,19 @@
 //! pins 4,5,6 and 7. If you don't use an external 3.3V power source,
 //! you can connect the +3.3V output on pin 36 to the SD card.
 //!
+//! Building with the `pio-spi` feature swaps SPI0 for a PIO0-driven SPI
+//! bus on the same pins, for boards that have already used up both SPI
+//! peripherals elsewhere.
+//!
+//! Building with the `gpt-partition` feature scans for a GPT label and a
+//! Microsoft Basic Data partition instead of the MBR layout below, for
+//! cards formatted by newer partitioning tools.
+//!
+//! Building with the `spi-dma` feature (SPI0 only, not combined with
+//! `pio-spi`) hands whole 512-byte sector transfers off to a DMA channel
+//! pair instead of blocking the core on byte-by-byte SPI transfers, which
+//! noticeably speeds up large file reads.
+//!
 //! SD Cards up to 2TB are supported by the `embedded_sdmmc` crate.
 //! I've tested this with a 64GB micro SD card.
 //!
@@ -47,15 +60,26 @@
 //! patterns.
 //!
 //! For every successful stage in the example the LED will blink long once.
-//! If everything is successful (9 long blink signals), the example will go
-//! into a loop and either blink in a _"short long"_ or _"short short long"_ pattern.
+//! Once every stage has succeeded, the example starts a USB CDC serial
+//! console (115200 8N1) with a handful of commands that operate on the
+//! card's root directory: `ls`, `cat <name>`, `write <name> <text>` and
+//! `rm <name>`.
+//!
+//! Building with the `uart-log` feature additionally mirrors every stage's
+//! `info!`/`error!` message as plain text on UART0 (TX/RX pins, 115200
+//! 8N1), so the same diagnostics that normally need a debug probe for
+//! `defmt`/RTT are also readable with nothing more than a USB-serial
+//! adapter.
 //!
 //! If there are 4 different error patterns, all with short blinking pulses:
 //!
 //! - **3 short blink (in a loop)**: Card size could not be retrieved.
 //! - **4 short blink (in a loop)**: Error getting volume/partition 0.
 //! - **5 short blink (in a loop)**: Error opening root directory.
-//! - **6 short blink (in a loop)**: Could not open file 'log.txt'.
+//! - **6 short blink (in a loop)**: Could not open 'boot.log' for the startup write check.
+//! - **7 short blink (in a loop)**: (`gpt-partition` only) The GPT scan
+//!   failed: a read error, no protective MBR, no `EFI PART` header, or no
+//!   Microsoft Basic Data partition in the card's GPT.
 //!
 //! See the `Cargo.toml` file for Copyright and license details.
 
@@ -99,7 +123,12 @@ use embedded_hal_bus::spi::{ExclusiveDevice, NoDelay};
 // Link in the embedded_sdmmc crate.
 // The `SdMmcSpi` is used for block level access to the card.
 // And the `VolumeManager` gives access to the FAT filesystem functions.
-use embedded_sdmmc::{SdCard, TimeSource, Timestamp, VolumeIdx, VolumeManager};
+use embedded_sdmmc::{BlockDevice, SdCard, TimeSource, Timestamp, VolumeIdx, VolumeManager};
+
+// Block, BlockCount and BlockIdx are only referenced by the `gpt` module
+// below, which pulls them in from here through `use super::`.
+#[cfg(feature = "gpt-partition")]
+use embedded_sdmmc::{Block, BlockCount, BlockIdx};
 
 // Dummy chip select to make the spi device happy lol
 use embedded_sdmmc::sdcard::DummyCsPin;
@@ -110,33 +139,499 @@ use embedded_sdmmc::filesystem::Mode;
 // DelayNs, used in Timers, to replace DelayMs and DelayUs defined in this file previously
 use embedded_hal::delay::DelayNs;
 
-/// A dummy timesource, which is mostly important for creating files.
-#[derive(Default)]
-pub struct DummyTimesource();
+// Brings `pac::DMA.split()` into scope for the `spi-dma` feature below.
+#[cfg(feature = "spi-dma")]
+use adafruit_feather_rp2040::hal::dma::DMAExt;
+
+// USB CDC serial console: `interrupt` names the `USBCTRL_IRQ` handler
+// below to cortex-m-rt, `NVIC` unmasks it once the console is ready.
+use adafruit_feather_rp2040::hal::pac::interrupt;
+use cortex_m::peripheral::NVIC;
+
+// UART log backend: brings in the config types for `UartPeripheral::enable`.
+#[cfg(feature = "uart-log")]
+use adafruit_feather_rp2040::hal::uart::{DataBits, StopBits, UartConfig};
+
+/// An `embedded_hal` `SpiBus` implementation driven entirely by one of the
+/// RP2040's PIO state machines, rather than the dedicated SPI0/SPI1
+/// peripherals.
+///
+/// This is useful once both hardware SPI blocks are already spoken for, or
+/// when the card needs to sit on pins that aren't wired to SPI function on
+/// this board. Only `cargo build --features pio-spi` pulls this in; by
+/// default the example still uses the fixed SPI0 peripheral above.
+#[cfg(feature = "pio-spi")]
+mod pio_spi {
+    use adafruit_feather_rp2040::hal::gpio::{FunctionPio0, Pin, PinId, PullNone};
+    use adafruit_feather_rp2040::hal::pio::{PIOBuilder, PinDir, ShiftDirection, StateMachine, StateMachineIndex, Tx, Rx, UninitStateMachine, PIOExt, Running};
+    use embedded_hal::spi::{ErrorType, SpiBus};
+    use fugit::HertzU32;
+
+    /// MODE_0, one FIFO word per byte: MOSI is driven from the OSR MSB,
+    /// the clock toggles around it, and MISO is sampled into the ISR on
+    /// the rising edge. `autopull`/`autopush` are both set to 8 bits so a
+    /// single `tx.write()`/`rx.read()` transfers exactly one byte.
+    ///
+    /// ```text
+    ///     .wrap_target
+    ///     out pins, 1  side 0 ; shift MOSI out while SCK is low
+    ///     in pins, 1   side 1 ; sample MISO in while SCK is high
+    ///     .wrap
+    /// ```
+    fn spi_mode0_program() -> pio::Program<32> {
+        pio_proc::pio_asm!(
+            ".side_set 1",
+            ".wrap_target",
+            "out pins, 1  side 0",
+            "in pins, 1   side 1",
+            ".wrap",
+        )
+        .program
+    }
+
+    /// A bit-banged-by-PIO SPI bus. `MOSI`/`MISO`/`SCK` can be any three
+    /// GPIOs reachable by the chosen PIO block.
+    pub struct PioSpiBus<P: PIOExt, SM: StateMachineIndex> {
+        tx: Tx<(P, SM)>,
+        rx: Rx<(P, SM)>,
+        sm: StateMachine<(P, SM), Running>,
+    }
+
+    impl<P: PIOExt, SM: StateMachineIndex> PioSpiBus<P, SM> {
+        /// Builds and starts the state machine. `clock_freq` is the system
+        /// clock driving the PIO block; `baudrate` is the desired SPI
+        /// clock (400 kHz for card init, 16 MHz once initialized).
+        pub fn new<MosiId: PinId, MisoId: PinId, SckId: PinId>(
+            pio: &mut adafruit_feather_rp2040::hal::pio::PIO<P>,
+            sm: UninitStateMachine<(P, SM)>,
+            mosi: Pin<MosiId, FunctionPio0, PullNone>,
+            miso: Pin<MisoId, FunctionPio0, PullNone>,
+            sck: Pin<SckId, FunctionPio0, PullNone>,
+            clock_freq: HertzU32,
+            baudrate: HertzU32,
+        ) -> Self {
+            let program = spi_mode0_program();
+            let installed = pio.install(&program).unwrap();
+
+            // Two PIO-clock cycles (out+in) make up one SPI bit, so the
+            // divider runs the state machine at 2x the target bit rate.
+            let div = clock_freq.to_Hz() as f32 / (2.0 * baudrate.to_Hz() as f32);
+
+            let (mut sm, rx, tx) = PIOBuilder::from_program(installed)
+                .out_pins(mosi.id().num, 1)
+                .in_pin_base(miso.id().num)
+                .side_set_pin_base(sck.id().num)
+                .out_shift_direction(ShiftDirection::Left)
+                .in_shift_direction(ShiftDirection::Left)
+                .autopull(true)
+                .autopush(true)
+                .pull_threshold(8)
+                .push_threshold(8)
+                .clock_divisor_fixed_point(div as u16, (div.fract() * 256.0) as u8)
+                .build(sm);
+
+            sm.set_pindirs([
+                (mosi.id().num, PinDir::Output),
+                (sck.id().num, PinDir::Output),
+                (miso.id().num, PinDir::Input),
+            ]);
+
+            Self { tx, rx, sm: sm.start() }
+        }
+
+        /// Re-derives the clock divider for a new baudrate, e.g. the
+        /// jump from 400 kHz card-init speed up to 16 MHz afterwards.
+        pub fn set_baudrate(&mut self, clock_freq: HertzU32, baudrate: HertzU32) {
+            let div = clock_freq.to_Hz() as f32 / (2.0 * baudrate.to_Hz() as f32);
+            self.sm.clock_divisor_fixed_point(div as u16, (div.fract() * 256.0) as u8);
+        }
+
+        fn transfer_byte(&mut self, out: u8) -> u8 {
+            while !self.tx.write((out as u32) << 24) {}
+            loop {
+                if let Some(word) = self.rx.read() {
+                    // `in_shift_direction(Left)` shifts each sampled bit
+                    // in at bit 0, so after 8 pushes the byte sits in the
+                    // low 8 bits, not the high 8 (that's only where TX
+                    // reads from, since OUT drains from the MSB end).
+                    return word as u8;
+                }
+            }
+        }
+    }
+
+    impl<P: PIOExt, SM: StateMachineIndex> ErrorType for PioSpiBus<P, SM> {
+        type Error = core::convert::Infallible;
+    }
+
+    impl<P: PIOExt, SM: StateMachineIndex> SpiBus<u8> for PioSpiBus<P, SM> {
+        fn read(&mut self, words: &mut [u8]) -> Result<(), Self::Error> {
+            for word in words.iter_mut() {
+                *word = self.transfer_byte(0xFF);
+            }
+            Ok(())
+        }
+
+        fn write(&mut self, words: &[u8]) -> Result<(), Self::Error> {
+            for word in words.iter() {
+                self.transfer_byte(*word);
+            }
+            Ok(())
+        }
+
+        fn transfer(&mut self, read: &mut [u8], write: &[u8]) -> Result<(), Self::Error> {
+            for (r, w) in read.iter_mut().zip(write.iter()) {
+                *r = self.transfer_byte(*w);
+            }
+            Ok(())
+        }
+
+        fn transfer_in_place(&mut self, words: &mut [u8]) -> Result<(), Self::Error> {
+            for word in words.iter_mut() {
+                *word = self.transfer_byte(*word);
+            }
+            Ok(())
+        }
+
+        fn flush(&mut self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+    }
+}
+
+/// A [`SpiBus`] wrapper that hands whole 512-byte sector transfers off to
+/// a paired TX/RX DMA channel instead of the blocking, byte-by-byte SPI
+/// transfers `SdCard` would otherwise do, so the core doesn't spend a
+/// read/write loop polling the SPI peripheral one byte at a time.
+///
+/// Besides whole sectors, `SdCard` also issues short transfers (commands,
+/// responses, CRC bytes, ...); anything that isn't exactly a full block
+/// just falls through to the plain blocking path on the wrapped bus.
+///
+/// Only `cargo build --features spi-dma` pulls this in.
+#[cfg(feature = "spi-dma")]
+mod dma_spi {
+    use adafruit_feather_rp2040::hal::dma::{bidirectional, SingleChannel};
+    use adafruit_feather_rp2040::hal::pac::SPI0;
+    use adafruit_feather_rp2040::hal::spi::{Enabled, Spi, ValidSpiPinout};
+    use embedded_hal::spi::{ErrorType, SpiBus};
+    use fugit::HertzU32;
+
+    /// Sized to match the SD card's block size so a full-sector
+    /// `read`/`write` always qualifies for the DMA path below.
+    const BLOCK_SIZE: usize = 512;
+
+    pub struct DmaSpiBus<PINS: ValidSpiPinout<SPI0>, TxCh: SingleChannel, RxCh: SingleChannel> {
+        // `Option` so ownership can move into the DMA transfer and back
+        // out again across `start()`/`wait()`.
+        bus: Option<Spi<Enabled, SPI0, PINS, 8>>,
+        tx_ch: Option<TxCh>,
+        rx_ch: Option<RxCh>,
+        // `bidirectional::Config` requires its buffers to be `'static`
+        // (the same way rp2040-hal's own `examples/spi_dma.rs` parks its
+        // TX/RX buffers in a `cortex_m::singleton!`), so a full block is
+        // copied in and out of these rather than handed to DMA directly.
+        tx_scratch: Option<&'static mut [u8; BLOCK_SIZE]>,
+        rx_scratch: Option<&'static mut [u8; BLOCK_SIZE]>,
+    }
+
+    impl<PINS: ValidSpiPinout<SPI0>, TxCh: SingleChannel, RxCh: SingleChannel> DmaSpiBus<PINS, TxCh, RxCh> {
+        pub fn new(bus: Spi<Enabled, SPI0, PINS, 8>, tx_ch: TxCh, rx_ch: RxCh) -> Self {
+            let tx_scratch = cortex_m::singleton!(: [u8; BLOCK_SIZE] = [0u8; BLOCK_SIZE]).unwrap();
+            let rx_scratch = cortex_m::singleton!(: [u8; BLOCK_SIZE] = [0u8; BLOCK_SIZE]).unwrap();
+            Self {
+                bus: Some(bus),
+                tx_ch: Some(tx_ch),
+                rx_ch: Some(rx_ch),
+                tx_scratch: Some(tx_scratch),
+                rx_scratch: Some(rx_scratch),
+            }
+        }
+
+        /// Clocks a full block in and out at once: one DMA channel feeds
+        /// `tx` into the SPI peripheral while the other captures whatever
+        /// comes back into `rx`, both running concurrently (via
+        /// `bidirectional::Config`) so the transfer takes one block's
+        /// worth of SPI clocks, not two.
+        fn dma_block_transfer(&mut self, tx: &[u8; BLOCK_SIZE], rx: &mut [u8; BLOCK_SIZE]) {
+            let tx_scratch = self.tx_scratch.take().unwrap();
+            let rx_scratch = self.rx_scratch.take().unwrap();
+            tx_scratch.copy_from_slice(tx);
+
+            let bus = self.bus.take().unwrap();
+            let tx_ch = self.tx_ch.take().unwrap();
+            let rx_ch = self.rx_ch.take().unwrap();
+
+            let transfer =
+                bidirectional::Config::new((tx_ch, rx_ch), tx_scratch, bus, rx_scratch).start();
+            let ((tx_ch, rx_ch), tx_scratch, bus, rx_scratch) = transfer.wait();
+
+            rx.copy_from_slice(&rx_scratch[..]);
 
-impl TimeSource for DummyTimesource {
-    // In theory you could use the RTC of the rp2040 here, if you had
-    // any external time synchronizing device.
+            self.tx_ch = Some(tx_ch);
+            self.rx_ch = Some(rx_ch);
+            self.bus = Some(bus);
+            self.tx_scratch = Some(tx_scratch);
+            self.rx_scratch = Some(rx_scratch);
+        }
+
+        /// Delegates to the wrapped `Spi`'s own `set_baudrate`, the same
+        /// way `PioSpiBus::set_baudrate` re-derives its clock divider for
+        /// the jump from 400 kHz card-init speed up to 16 MHz afterwards.
+        pub fn set_baudrate(&mut self, clock_freq: HertzU32, baudrate: HertzU32) -> HertzU32 {
+            self.bus.as_mut().unwrap().set_baudrate(clock_freq, baudrate)
+        }
+    }
+
+    impl<PINS: ValidSpiPinout<SPI0>, TxCh: SingleChannel, RxCh: SingleChannel> ErrorType for DmaSpiBus<PINS, TxCh, RxCh> {
+        type Error = core::convert::Infallible;
+    }
+
+    impl<PINS: ValidSpiPinout<SPI0>, TxCh: SingleChannel, RxCh: SingleChannel> SpiBus<u8> for DmaSpiBus<PINS, TxCh, RxCh> {
+        fn read(&mut self, words: &mut [u8]) -> Result<(), Self::Error> {
+            if let Ok(chunk) = <&mut [u8; BLOCK_SIZE]>::try_from(&mut words[..]) {
+                // Drive the clock with dummy 0xFF bytes while DMA
+                // captures whatever the card shifts back on MISO.
+                self.dma_block_transfer(&[0xFFu8; BLOCK_SIZE], chunk);
+            } else {
+                self.bus.as_mut().unwrap().read(words)?;
+            }
+            Ok(())
+        }
+
+        fn write(&mut self, words: &[u8]) -> Result<(), Self::Error> {
+            if let Ok(chunk) = <&[u8; BLOCK_SIZE]>::try_from(words) {
+                let mut discard = [0u8; BLOCK_SIZE];
+                self.dma_block_transfer(chunk, &mut discard);
+            } else {
+                self.bus.as_mut().unwrap().write(words)?;
+            }
+            Ok(())
+        }
+
+        fn transfer(&mut self, read: &mut [u8], write: &[u8]) -> Result<(), Self::Error> {
+            if let (Ok(read_chunk), Ok(write_chunk)) = (
+                <&mut [u8; BLOCK_SIZE]>::try_from(&mut read[..]),
+                <&[u8; BLOCK_SIZE]>::try_from(write),
+            ) {
+                self.dma_block_transfer(write_chunk, read_chunk);
+            } else {
+                self.bus.as_mut().unwrap().transfer(read, write)?;
+            }
+            Ok(())
+        }
+
+        fn transfer_in_place(&mut self, words: &mut [u8]) -> Result<(), Self::Error> {
+            if let Ok(chunk) = <&mut [u8; BLOCK_SIZE]>::try_from(&mut words[..]) {
+                let tx = *chunk;
+                self.dma_block_transfer(&tx, chunk);
+            } else {
+                self.bus.as_mut().unwrap().transfer_in_place(words)?;
+            }
+            Ok(())
+        }
+
+        fn flush(&mut self) -> Result<(), Self::Error> {
+            self.bus.as_mut().unwrap().flush()
+        }
+    }
+}
+
+/// Build-time default used to seed the RTC in `main` below. With no
+/// external time source (GPS, NTP, a host PC over USB, ...) to sync
+/// against, every timestamp on the card is relative to this moment.
+const BUILD_DATETIME: hal::rtc::DateTime = hal::rtc::DateTime {
+    year: 2026,
+    month: 1,
+    day: 1,
+    day_of_week: hal::rtc::DayOfWeek::Thursday,
+    hour: 0,
+    minute: 0,
+    second: 0,
+};
+
+/// A [`TimeSource`] backed by the RP2040's on-chip RTC, so files written
+/// to the card carry a real modification timestamp instead of the Unix
+/// epoch.
+///
+/// The RTC's own date/time registers store a full year and 1-indexed
+/// month/day, while `embedded_sdmmc::Timestamp` wants years counted from
+/// 1970 and zero-indexed months/days, so `get_timestamp` does that
+/// conversion on every call.
+pub struct RtcTimesource<'a> {
+    rtc: &'a hal::rtc::RealTimeClock,
+}
+
+impl<'a> RtcTimesource<'a> {
+    pub fn new(rtc: &'a hal::rtc::RealTimeClock) -> Self {
+        Self { rtc }
+    }
+}
+
+impl<'a> TimeSource for RtcTimesource<'a> {
     fn get_timestamp(&self) -> Timestamp {
+        // If the RTC hasn't been seeded yet (or momentarily isn't
+        // running), fall back to the same build-time default it was
+        // started with rather than reporting garbage.
+        let now = self.rtc.now().unwrap_or(BUILD_DATETIME);
         Timestamp {
-            year_since_1970: 0,
-            zero_indexed_month: 0,
-            zero_indexed_day: 0,
-            hours: 0,
-            minutes: 0,
-            seconds: 0,
+            year_since_1970: (now.year - 1970) as u8,
+            zero_indexed_month: now.month - 1,
+            zero_indexed_day: now.day - 1,
+            hours: now.hour,
+            minutes: now.minute,
+            seconds: now.second,
         }
     }
 }
 
 // Setup some blinking codes:
 const BLINK_OK_LONG: [u8; 1] = [8u8];
-const BLINK_OK_SHORT_LONG: [u8; 4] = [1u8, 0u8, 6u8, 0u8];
-const BLINK_OK_SHORT_SHORT_LONG: [u8; 6] = [1u8, 0u8, 1u8, 0u8, 6u8, 0u8];
 const BLINK_ERR_3_SHORT: [u8; 6] = [1u8, 0u8, 1u8, 0u8, 1u8, 0u8];
 const BLINK_ERR_4_SHORT: [u8; 8] = [1u8, 0u8, 1u8, 0u8, 1u8, 0u8, 1u8, 0u8];
 const BLINK_ERR_5_SHORT: [u8; 10] = [1u8, 0u8, 1u8, 0u8, 1u8, 0u8, 1u8, 0u8, 1u8, 0u8];
 const BLINK_ERR_6_SHORT: [u8; 12] = [1u8, 0u8, 1u8, 0u8, 1u8, 0u8, 1u8, 0u8, 1u8, 0u8, 1u8, 0u8];
+// 7 short blinks: scanning the card's GPT failed (any `gpt::GptError`:
+// read error, no protective MBR, no GPT header, or no Basic Data partition).
+#[cfg(feature = "gpt-partition")]
+const BLINK_ERR_7_SHORT: [u8; 14] = [
+    1u8, 0u8, 1u8, 0u8, 1u8, 0u8, 1u8, 0u8, 1u8, 0u8, 1u8, 0u8, 1u8, 0u8,
+];
+
+/// GPT partition-table support for the volume-opening path above, gated
+/// behind the `gpt-partition` feature the same way `pio_spi`/`dma_spi`
+/// gate their own optional backends.
+#[cfg(feature = "gpt-partition")]
+mod gpt {
+    use super::{Block, BlockCount, BlockDevice, BlockIdx, TimeSource, VolumeManager};
+
+    /// The Microsoft Basic Data partition type GUID (`EBD0A0A2-B9E5-4433-87C0-68B6B72699C7`),
+    /// stored mixed-endian the way GPT partition entries encode it on disk.
+    const GPT_BASIC_DATA_GUID: [u8; 16] = [
+        0xA2, 0xA0, 0xD0, 0xEB, 0xE5, 0xB9, 0x33, 0x44, 0x87, 0xC0, 0x68, 0xB6, 0xB7, 0x26, 0x99,
+        0xC7,
+    ];
+
+    /// Errors that can happen while hunting for a GPT data partition.
+    #[derive(Debug)]
+    pub enum GptError<E> {
+        /// Couldn't read a block off the card.
+        Device(E),
+        /// LBA 0 didn't contain a protective MBR (single `0xEE` entry).
+        NoProtectiveMbr,
+        /// LBA 1 didn't start with the `EFI PART` signature.
+        NoGptSignature,
+        /// Walked every partition entry and found none with the Microsoft
+        /// Basic Data type GUID.
+        NoBasicDataPartition,
+    }
+
+    /// A [`BlockDevice`] that transparently shifts every block index by a
+    /// fixed number of blocks, so the existing FAT32-only [`VolumeManager`]
+    /// can be pointed straight at a GPT partition's first LBA without having
+    /// to understand GPT itself.
+    pub struct PartitionOffsetDevice<D> {
+        inner: D,
+        start_block: BlockIdx,
+    }
+
+    impl<D: BlockDevice> BlockDevice for PartitionOffsetDevice<D> {
+        type Error = D::Error;
+
+        fn read(
+            &self,
+            blocks: &mut [Block],
+            start_block_idx: BlockIdx,
+            reason: &str,
+        ) -> Result<(), Self::Error> {
+            self.inner.read(
+                blocks,
+                BlockIdx(self.start_block.0 + start_block_idx.0),
+                reason,
+            )
+        }
+
+        fn write(&self, blocks: &[Block], start_block_idx: BlockIdx) -> Result<(), Self::Error> {
+            self.inner
+                .write(blocks, BlockIdx(self.start_block.0 + start_block_idx.0))
+        }
+
+        fn num_blocks(&self) -> Result<BlockCount, Self::Error> {
+            let total = self.inner.num_blocks()?;
+            Ok(BlockCount(total.0.saturating_sub(self.start_block.0)))
+        }
+    }
+
+    /// Scans a GPT-labelled card for the first Microsoft Basic Data partition
+    /// and returns a [`VolumeManager`] whose block 0 is that partition's FAT32
+    /// boot sector, so `open_volume(VolumeIdx(0))` continues to work unchanged.
+    ///
+    /// This is the GPT counterpart to plain `open_volume(VolumeIdx(0))`, which
+    /// only understands the MBR `W95 FAT32 (LBA)` layout documented above.
+    pub fn open_volume_gpt<D: BlockDevice, T: TimeSource>(
+        device: D,
+        timesource: T,
+    ) -> Result<VolumeManager<PartitionOffsetDevice<D>, T>, GptError<D::Error>> {
+        let mut block = [Block::new()];
+
+        // LBA 0: a protective MBR with a single 0xEE ("GPT protective")
+        // partition entry, covering the whole disk.
+        device
+            .read(&mut block, BlockIdx(0), "gpt protective mbr")
+            .map_err(GptError::Device)?;
+        let mbr = &block[0].contents;
+        if mbr[450] != 0xEE {
+            return Err(GptError::NoProtectiveMbr);
+        }
+
+        // LBA 1: the GPT header itself.
+        device
+            .read(&mut block, BlockIdx(1), "gpt header")
+            .map_err(GptError::Device)?;
+        let header = &block[0].contents;
+        if &header[0..8] != b"EFI PART" {
+            return Err(GptError::NoGptSignature);
+        }
+        let entries_lba = u64::from_le_bytes(header[72..80].try_into().unwrap());
+        let num_entries = u32::from_le_bytes(header[80..84].try_into().unwrap());
+        let entry_size = u32::from_le_bytes(header[84..88].try_into().unwrap()) as usize;
+
+        // Walk the partition-entry array, one 512-byte block (and possibly
+        // several entries) at a time.
+        let entries_per_block = Block::LEN / entry_size;
+        let num_blocks = (num_entries as usize).div_ceil(entries_per_block);
+        for block_offset in 0..num_blocks {
+            device
+                .read(
+                    &mut block,
+                    BlockIdx(entries_lba as u32 + block_offset as u32),
+                    "gpt partition entries",
+                )
+                .map_err(GptError::Device)?;
+            for slot in 0..entries_per_block {
+                let entry_idx = block_offset * entries_per_block + slot;
+                if entry_idx >= num_entries as usize {
+                    break;
+                }
+                let entry = &block[0].contents[slot * entry_size..(slot + 1) * entry_size];
+                let type_guid = &entry[0..16];
+                if type_guid.iter().all(|b| *b == 0) {
+                    continue; // unused entry
+                }
+                if type_guid == GPT_BASIC_DATA_GUID {
+                    let first_lba = u64::from_le_bytes(entry[32..40].try_into().unwrap());
+                    let partition_device = PartitionOffsetDevice {
+                        inner: device,
+                        start_block: BlockIdx(first_lba as u32),
+                    };
+                    return Ok(VolumeManager::new(partition_device, timesource));
+                }
+            }
+        }
+
+        Err(GptError::NoBasicDataPartition)
+    }
+}
 
 fn blink_signals(
     pin: &mut dyn embedded_hal::digital::OutputPin<Error = core::convert::Infallible>,
@@ -173,6 +668,274 @@ fn blink_signals_loop(
     }
 }
 
+/// Byte queues shared between `main` and the `USBCTRL_IRQ` handler below.
+///
+/// `VolumeManager` (and everything built on it) isn't `Send`, so it can't
+/// live behind the IRQ — instead the interrupt only ever fills `RX` from
+/// the host and drains `TX` back out to it, and all of the actual
+/// filesystem work happens down in `main`'s command loop.
+mod usb_console {
+    use core::cell::RefCell;
+    use critical_section::Mutex;
+    use heapless::{Deque, String};
+
+    pub static RX: Mutex<RefCell<Deque<u8, 256>>> = Mutex::new(RefCell::new(Deque::new()));
+    pub static TX: Mutex<RefCell<Deque<u8, 256>>> = Mutex::new(RefCell::new(Deque::new()));
+
+    /// Queues `bytes` for the IRQ handler to ship out over serial.
+    pub fn write(bytes: &[u8]) {
+        critical_section::with(|cs| {
+            let mut tx = TX.borrow_ref_mut(cs);
+            for b in bytes {
+                let _ = tx.push_back(*b);
+            }
+        });
+    }
+
+    /// Pulls one newline-terminated command line out of `RX`, if a full
+    /// one has arrived yet. Leaves partial lines queued for next time.
+    pub fn read_line() -> Option<String<128>> {
+        critical_section::with(|cs| {
+            let mut rx = RX.borrow_ref_mut(cs);
+            if !rx.iter().any(|b| *b == b'\n') {
+                return None;
+            }
+            let mut line = String::new();
+            while let Some(b) = rx.pop_front() {
+                if b == b'\n' {
+                    break;
+                }
+                if b != b'\r' {
+                    let _ = line.push(b as char);
+                }
+            }
+            Some(line)
+        })
+    }
+}
+
+static USB_SERIAL: critical_section::Mutex<
+    core::cell::RefCell<Option<usbd_serial::SerialPort<'static, hal::usb::UsbBus>>>,
+> = critical_section::Mutex::new(core::cell::RefCell::new(None));
+static USB_DEVICE: critical_section::Mutex<
+    core::cell::RefCell<Option<usb_device::device::UsbDevice<'static, hal::usb::UsbBus>>>,
+> = critical_section::Mutex::new(core::cell::RefCell::new(None));
+
+/// Drains whatever the host just sent into [`usb_console::RX`], and
+/// whatever the command loop queued in [`usb_console::TX`] out to the
+/// host. No filesystem access happens in here.
+#[allow(non_snake_case)]
+#[interrupt]
+fn USBCTRL_IRQ() {
+    critical_section::with(|cs| {
+        let mut serial_ref = USB_SERIAL.borrow_ref_mut(cs);
+        let mut device_ref = USB_DEVICE.borrow_ref_mut(cs);
+        let (Some(serial), Some(device)) = (serial_ref.as_mut(), device_ref.as_mut()) else {
+            return;
+        };
+
+        if device.poll(&mut [serial]) {
+            let mut buf = [0u8; 64];
+            if let Ok(count) = serial.read(&mut buf) {
+                let mut rx = usb_console::RX.borrow_ref_mut(cs);
+                for b in &buf[..count] {
+                    let _ = rx.push_back(*b);
+                }
+            }
+        }
+
+        let mut tx = usb_console::TX.borrow_ref_mut(cs);
+        while let Some(b) = tx.pop_front() {
+            match serial.write(&[b]) {
+                Ok(_) => {}
+                Err(_) => {
+                    // FIFO's full for now; leave the byte queued and
+                    // retry on the next IRQ.
+                    let _ = tx.push_front(b);
+                    break;
+                }
+            }
+        }
+    });
+}
+
+/// TX-only byte queue shared between `main` and the `UART0_IRQ` handler
+/// below. Unlike `usb_console`, nothing ever needs to come back the other
+/// way — this backend only mirrors log text for anyone without a debug
+/// probe, so the queue is fed here and drained purely by the interrupt.
+///
+/// Only `cargo build --features uart-log` pulls this in.
+#[cfg(feature = "uart-log")]
+mod uart_log {
+    use core::cell::RefCell;
+    use core::fmt::Write;
+    use critical_section::Mutex;
+    use heapless::Deque;
+
+    pub static TX: Mutex<RefCell<Deque<u8, 512>>> = Mutex::new(RefCell::new(Deque::new()));
+
+    /// Queues `bytes` for the UART TX interrupt to drain onto the wire.
+    pub fn write(bytes: &[u8]) {
+        critical_section::with(|cs| {
+            let mut tx = TX.borrow_ref_mut(cs);
+            for b in bytes {
+                let _ = tx.push_back(*b);
+            }
+        });
+    }
+
+    /// Formats `args` as `line\r\n` and queues it.
+    pub fn write_line(args: core::fmt::Arguments) {
+        let mut line: heapless::String<128> = heapless::String::new();
+        let _ = write!(line, "{}\r\n", args);
+        write(line.as_bytes());
+    }
+}
+
+/// Mirrors a `format_args!`-style log line over the `uart-log` UART
+/// backend; compiles away to nothing when the feature isn't enabled. Takes
+/// the same arguments as `info!`/`error!` so it reads as a second line
+/// right underneath them at each call site.
+macro_rules! uart_log_line {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "uart-log")]
+        {
+            uart_log::write_line(format_args!($($arg)*));
+        }
+    };
+}
+
+/// The UART peripheral the `uart-log` backend writes to, handed over to
+/// `UART0_IRQ` the same way `USB_SERIAL`/`USB_DEVICE` are handed to
+/// `USBCTRL_IRQ` above.
+#[cfg(feature = "uart-log")]
+static UART_LOG: critical_section::Mutex<
+    core::cell::RefCell<
+        Option<
+            hal::uart::UartPeripheral<
+                hal::uart::Enabled,
+                pac::UART0,
+                (
+                    gpio::Pin<gpio::bank0::Gpio0, gpio::FunctionUart, gpio::PullNone>,
+                    gpio::Pin<gpio::bank0::Gpio1, gpio::FunctionUart, gpio::PullNone>,
+                ),
+            >,
+        >,
+    >,
+> = critical_section::Mutex::new(core::cell::RefCell::new(None));
+
+/// Drains `uart_log::TX` into UART0's FIFO whenever there's room for it.
+/// Fires only on the TX interrupt (`enable_tx_interrupt` in `main`); nothing is
+/// ever read back in, since this backend is mirror-only.
+#[cfg(feature = "uart-log")]
+#[allow(non_snake_case)]
+#[interrupt]
+fn UART0_IRQ() {
+    critical_section::with(|cs| {
+        let mut uart_ref = UART_LOG.borrow_ref_mut(cs);
+        let Some(uart) = uart_ref.as_mut() else {
+            return;
+        };
+
+        let mut tx = uart_log::TX.borrow_ref_mut(cs);
+        while let Some(b) = tx.pop_front() {
+            match uart.write_raw(&[b]) {
+                Ok(_) => {}
+                Err(_) => {
+                    // FIFO's full for now; leave the byte queued and
+                    // retry on the next IRQ.
+                    let _ = tx.push_front(b);
+                    break;
+                }
+            }
+        }
+    });
+}
+
+/// Renders `prefix: {e:?}\r\n` and queues it for the host.
+fn report_error(prefix: &str, e: impl core::fmt::Debug) {
+    use core::fmt::Write;
+    let mut line: heapless::String<96> = heapless::String::new();
+    // Qualified as `core::write!`: the module-level `use defmt::*` above
+    // also brings in `defmt::write!`, which expects a `defmt::Formatter`
+    // rather than anything implementing `core::fmt::Write`.
+    let _ = core::write!(line, "{}: {:?}\r\n", prefix, e);
+    usb_console::write(line.as_bytes());
+}
+
+/// Parses and runs one console command line against the already-opened
+/// root directory, queuing its output for the host over `usb_console`.
+///
+/// Supported commands: `ls`, `cat <name>`, `write <name> <text>`, `rm <name>`.
+fn dispatch_command<D: embedded_sdmmc::BlockDevice, T: TimeSource>(
+    line: &str,
+    dir: &mut embedded_sdmmc::Directory<'_, D, T, 4, 4, 1>,
+) {
+    let mut parts = line.splitn(3, ' ');
+    match parts.next().unwrap_or("") {
+        "ls" => {
+            let result = dir.iterate_dir(|ent| {
+                usb_console::write(ent.name.base_name());
+                usb_console::write(b".");
+                usb_console::write(ent.name.extension());
+                usb_console::write(b"\r\n");
+            });
+            if let Err(e) = result {
+                report_error("ls: error", e);
+            }
+        }
+        "cat" => {
+            let Some(name) = parts.next() else {
+                usb_console::write(b"usage: cat <name>\r\n");
+                return;
+            };
+            match dir.open_file_in_dir(name, Mode::ReadOnly) {
+                Ok(mut file) => {
+                    let mut buffer = [0u8; 64];
+                    while !file.is_eof() {
+                        match file.read(&mut buffer) {
+                            Ok(len) => usb_console::write(&buffer[..len]),
+                            Err(_) => break,
+                        }
+                    }
+                    usb_console::write(b"\r\n");
+                }
+                Err(e) => report_error("cat: error", e),
+            }
+        }
+        "write" => {
+            let Some(name) = parts.next() else {
+                usb_console::write(b"usage: write <name> <text>\r\n");
+                return;
+            };
+            let text = parts.next().unwrap_or("");
+            match dir.open_file_in_dir(name, Mode::ReadWriteCreateOrTruncate) {
+                Ok(mut file) => match file.write(text.as_bytes()) {
+                    Ok(()) => usb_console::write(b"ok\r\n"),
+                    Err(e) => report_error("write: error", e),
+                },
+                Err(e) => report_error("write: error", e),
+            }
+        }
+        "rm" => {
+            let Some(name) = parts.next() else {
+                usb_console::write(b"usage: rm <name>\r\n");
+                return;
+            };
+            match dir.delete_file_in_dir(name) {
+                Ok(()) => usb_console::write(b"ok\r\n"),
+                Err(e) => report_error("rm: error", e),
+            }
+        }
+        "" => {}
+        other => {
+            usb_console::write(b"unknown command: ");
+            usb_console::write(other.as_bytes());
+            usb_console::write(b"\r\n");
+        }
+    }
+}
+
 #[entry]
 fn main() -> ! {
     info!("Program start");
@@ -213,22 +976,85 @@ fn main() -> ! {
     // Set the LED to be an output
     let mut led_pin = pins.d13.into_push_pull_output();
 
-    // Set up our SPI pins into the correct mode
-    let spi_sclk: gpio::Pin<_, gpio::FunctionSpi, gpio::PullNone> = pins.sclk.reconfigure();
-    let spi_mosi: gpio::Pin<_, gpio::FunctionSpi, gpio::PullNone> = pins.mosi.reconfigure();
-    let spi_miso: gpio::Pin<_, gpio::FunctionSpi, gpio::PullUp> = pins.miso.reconfigure();
+    // Bring up the UART log backend as early as possible so every stage
+    // from here on can mirror its `info!`/`error!` text onto the wire.
+    #[cfg(feature = "uart-log")]
+    {
+        let uart_pins = (
+            pins.tx.reconfigure::<gpio::FunctionUart, gpio::PullNone>(),
+            pins.rx.reconfigure::<gpio::FunctionUart, gpio::PullNone>(),
+        );
+        let mut uart = hal::uart::UartPeripheral::new(pac.UART0, uart_pins, &mut pac.RESETS)
+            .enable(
+                UartConfig::new(115200.Hz(), DataBits::Eight, None, StopBits::One),
+                clocks.peripheral_clock.freq(),
+            )
+            .unwrap();
+        uart.enable_tx_interrupt();
+
+        critical_section::with(|cs| {
+            UART_LOG.borrow_ref_mut(cs).replace(uart);
+        });
+
+        // Safety: `UART0_IRQ` only ever touches `UART_LOG` and
+        // `uart_log::TX`, both behind `critical_section` mutexes, and
+        // `UART_LOG` is already populated by the time the interrupt is
+        // unmasked.
+        unsafe {
+            NVIC::unmask(pac::Interrupt::UART0_IRQ);
+        }
+    }
+
     let spi_cs = pins.d25.into_push_pull_output();
-    
-    // Create a SpiBus on SPI0
-    let spi_bus = spi::Spi::<_, _, _, 8>::new(pac.SPI0, (spi_mosi, spi_miso, spi_sclk));
 
-    // Exchange the uninitialised SPI bus for an initialised one
-    let spi_bus = spi_bus.init(
-        &mut pac.RESETS,
-        clocks.peripheral_clock.freq(),
-        400.kHz(), // card initialization happens at low baud rate
-        embedded_hal::spi::MODE_0,
-    );
+    #[cfg(not(feature = "pio-spi"))]
+    let spi_bus = {
+        // Set up our SPI pins into the correct mode
+        let spi_sclk: gpio::Pin<_, gpio::FunctionSpi, gpio::PullNone> = pins.sclk.reconfigure();
+        let spi_mosi: gpio::Pin<_, gpio::FunctionSpi, gpio::PullNone> = pins.mosi.reconfigure();
+        let spi_miso: gpio::Pin<_, gpio::FunctionSpi, gpio::PullUp> = pins.miso.reconfigure();
+
+        // Create a SpiBus on SPI0
+        let spi_bus = spi::Spi::<_, _, _, 8>::new(pac.SPI0, (spi_mosi, spi_miso, spi_sclk));
+
+        // Exchange the uninitialised SPI bus for an initialised one
+        spi_bus.init(
+            &mut pac.RESETS,
+            clocks.peripheral_clock.freq(),
+            400.kHz(), // card initialization happens at low baud rate
+            embedded_hal::spi::MODE_0,
+        )
+    };
+
+    // Hand full-block transfers off to a TX/RX DMA channel pair instead of
+    // blocking the core one byte at a time; only applies to the SPI0
+    // path above since DMA needs the peripheral's own FIFO as a target.
+    #[cfg(all(not(feature = "pio-spi"), feature = "spi-dma"))]
+    let spi_bus = {
+        let dma = pac.DMA.split(&mut pac.RESETS);
+        dma_spi::DmaSpiBus::new(spi_bus, dma.ch0, dma.ch1)
+    };
+
+    // Same pins SPI0 would have used, but clocked out of PIO0 SM0 instead,
+    // so boards that have already consumed SPI0/SPI1 (or that want the
+    // card on non-SPI-capable pins) can still run this example.
+    #[cfg(feature = "pio-spi")]
+    let spi_bus = {
+        let spi_sclk: gpio::Pin<_, gpio::FunctionPio0, gpio::PullNone> = pins.sclk.reconfigure();
+        let spi_mosi: gpio::Pin<_, gpio::FunctionPio0, gpio::PullNone> = pins.mosi.reconfigure();
+        let spi_miso: gpio::Pin<_, gpio::FunctionPio0, gpio::PullNone> = pins.miso.reconfigure();
+
+        let (mut pio, sm0, _, _, _) = pac.PIO0.split(&mut pac.RESETS);
+        pio_spi::PioSpiBus::new(
+            &mut pio,
+            sm0,
+            spi_mosi,
+            spi_miso,
+            spi_sclk,
+            clocks.peripheral_clock.freq(),
+            400.kHz(), // card initialization happens at low baud rate
+        )
+    };
 
     // Make a SpiDevice for the SdCard
     let spi_device = ExclusiveDevice::new(spi_bus, DummyCsPin, NoDelay);
@@ -242,16 +1068,47 @@ fn main() -> ! {
     );
 
     info!("Initialize SPI SD/MMC data structures...");
+    uart_log_line!("Initialize SPI SD/MMC data structures...");
     let sdcard = SdCard::new(spi_device, spi_cs, delay);
-    let mut volume_mgr = VolumeManager::new(sdcard, DummyTimesource::default());
+
+    // Seed the RTC with our build-time default so files get a sensible
+    // timestamp even with nothing external to sync the clock against.
+    let rtc = hal::rtc::RealTimeClock::new(
+        pac.RTC,
+        clocks.rtc_clock,
+        &mut pac.RESETS,
+        BUILD_DATETIME,
+    )
+    .unwrap();
+
+    // By default we expect the classic MBR/DOS partition table documented
+    // above. Cards formatted with a GPT label instead (as most modern
+    // `fdisk`/`gdisk` defaults produce) need `--features gpt-partition`.
+    #[cfg(not(feature = "gpt-partition"))]
+    let mut volume_mgr = VolumeManager::new(sdcard, RtcTimesource::new(&rtc));
+
+    #[cfg(feature = "gpt-partition")]
+    let mut volume_mgr = match gpt::open_volume_gpt(sdcard, RtcTimesource::new(&rtc)) {
+        Ok(volume_mgr) => volume_mgr,
+        Err(e) => {
+            error!("Error scanning GPT partition table: {}", defmt::Debug2Format(&e));
+            uart_log_line!("Error scanning GPT partition table: {:?}", e);
+            blink_signals_loop(&mut led_pin, &mut delay, &BLINK_ERR_7_SHORT);
+        }
+    };
 
     blink_signals(&mut led_pin, &mut delay, &BLINK_OK_LONG);
 
     info!("Init SD card controller and retrieve card size...");
+    uart_log_line!("Init SD card controller and retrieve card size...");
     match volume_mgr.device().num_bytes() {
-        Ok(size) => info!("card size is {} bytes", size),
+        Ok(size) => {
+            info!("card size is {} bytes", size);
+            uart_log_line!("card size is {} bytes", size);
+        }
         Err(e) => {
             error!("Error retrieving card size: {}", defmt::Debug2Format(&e));
+            uart_log_line!("Error retrieving card size: {:?}", e);
             blink_signals_loop(&mut led_pin, &mut delay, &BLINK_ERR_3_SHORT);
         }
     }
@@ -263,11 +1120,16 @@ fn main() -> ! {
         .device()
         .spi(|spi_device| spi_device.bus_mut().set_baudrate(clocks.peripheral_clock.freq(), 16.MHz()));
 
+    // Under `gpt-partition`, `volume_mgr` is already seated at the data
+    // partition's first LBA (see `open_volume_gpt` above), so this call
+    // reads straight into its FAT32 boot sector instead of an MBR.
     info!("Getting Volume 0...");
+    uart_log_line!("Getting Volume 0...");
     let mut volume = match volume_mgr.open_volume(VolumeIdx(0)) {
         Ok(v) => v,
         Err(e) => {
             error!("Error getting volume 0: {}", defmt::Debug2Format(&e));
+            uart_log_line!("Error getting volume 0: {:?}", e);
             blink_signals_loop(&mut led_pin, &mut delay, &BLINK_ERR_4_SHORT);
         }
     };
@@ -280,11 +1142,13 @@ fn main() -> ! {
         Ok(dir) => dir,
         Err(e) => {
             error!("Error opening root dir: {}", defmt::Debug2Format(&e));
+            uart_log_line!("Error opening root dir: {:?}", e);
             blink_signals_loop(&mut led_pin, &mut delay, &BLINK_ERR_5_SHORT);
         }
     };
 
     info!("Root directory opened!");
+    uart_log_line!("Root directory opened!");
     blink_signals(&mut led_pin, &mut delay, &BLINK_OK_LONG);
 
     // This shows how to iterate through the directory and how
@@ -299,65 +1163,71 @@ fn main() -> ! {
 
     blink_signals(&mut led_pin, &mut delay, &BLINK_OK_LONG);
 
-    let mut successful_read = false;
-
-    // Next we going to read a file from the SD card:
-    if let Ok(mut file) = dir.open_file_in_dir("log.txt", Mode::ReadOnly) {
-        while !file.is_eof() {
-            let mut buffer = [0u8; 32];
-            let offset = file.offset();
-            let mut len = file.read(&mut buffer).unwrap(); //fixme better way to do this or no?
-            info!("{:08x} {:02x}", offset, &buffer[0..len]);
-            while len < buffer.len() {
-                info!("\t");
-                len += 1;
-            }
-            info!(" |");
-            for b in buffer.iter() { // todo improve printout of each line in here. Maybe just info!() the entire buffer at once?
-                let ch = char::from(*b);
-                if ch.is_ascii_graphic() {
-                    info!("{}", ch);
-                } else {
-                    info!(".");
-                }
-            }
-            info!("|\n");
-
-            if len > 2 && buffer[0] == b"t"[0] && buffer[1] == b"e"[0] {successful_read = true;} // scuffed but we should only have one line of data anyways
-        }
-    }
-
-    blink_signals(&mut led_pin, &mut delay, &BLINK_OK_LONG);
-
-    let file = dir.open_file_in_dir("log.txt", Mode::ReadWriteCreateOrTruncate);
-    match file {
+    // Quick sanity check that the card is actually writable before we
+    // hand control over to the interactive console below.
+    match dir.open_file_in_dir("boot.log", Mode::ReadWriteCreateOrTruncate) {
         Ok(mut file) => {
-            file
-                .write(b"test log data")
-                .unwrap();
+            file.write(b"console started\n").unwrap();
         }
         Err(e) => {
-            error!("Error opening file 'log.txt': {}", defmt::Debug2Format(&e));
+            error!("Error opening file 'boot.log': {}", defmt::Debug2Format(&e));
+            uart_log_line!("Error opening file 'boot.log': {:?}", e);
             blink_signals_loop(&mut led_pin, &mut delay, &BLINK_ERR_6_SHORT);
         }
     }
 
     blink_signals(&mut led_pin, &mut delay, &BLINK_OK_LONG);
 
-    if successful_read {
-        info!("Successfully read previously written file 'log.txt'");
-    } else {
-        info!("Could not read file, which is ok for the first run.");
-        info!("Reboot the pico!");
+    // `UsbBusAllocator` needs a `'static` lifetime since the `USBCTRL_IRQ`
+    // handler above reaches back into it for as long as the device is plugged
+    // in, so it's parked in a `cortex_m::singleton!` the way rp-hal's own
+    // USB examples do.
+    let usb_bus = cortex_m::singleton!(: usb_device::bus::UsbBusAllocator<hal::usb::UsbBus> =
+        usb_device::bus::UsbBusAllocator::new(hal::usb::UsbBus::new(
+            pac.USBCTRL_REGS,
+            pac.USBCTRL_DPRAM,
+            clocks.usb_clock,
+            true,
+            &mut pac.RESETS,
+        ))
+    )
+    .unwrap();
+
+    let serial = usbd_serial::SerialPort::new(usb_bus);
+    let usb_dev = usb_device::device::UsbDeviceBuilder::new(
+        usb_bus,
+        usb_device::device::UsbVidPid(0x16c0, 0x27dd),
+    )
+    .strings(&[usb_device::device::StringDescriptors::default()
+        .manufacturer("Adafruit")
+        .product("Feather RP2040 SD card console")
+        .serial_number("sdcard-console")])
+    .unwrap()
+    .device_class(usbd_serial::USB_CLASS_CDC)
+    .build();
+
+    critical_section::with(|cs| {
+        USB_SERIAL.borrow_ref_mut(cs).replace(serial);
+        USB_DEVICE.borrow_ref_mut(cs).replace(usb_dev);
+    });
+
+    // Safety: the handler only touches `USB_SERIAL`/`USB_DEVICE` and the
+    // `usb_console` queues, all of which are behind `critical_section`
+    // mutexes, and both statics above are already populated.
+    unsafe {
+        NVIC::unmask(pac::Interrupt::USBCTRL_IRQ);
     }
 
+    info!("USB serial console ready: ls, cat <name>, write <name> <text>, rm <name>");
+    uart_log_line!("USB serial console ready: ls, cat <name>, write <name> <text>, rm <name>");
+    blink_signals(&mut led_pin, &mut delay, &BLINK_OK_LONG);
+
+    // `VolumeManager`/`Directory` aren't `Send`, so all filesystem work
+    // happens right here on the main loop; `USBCTRL_IRQ` only ever moves
+    // bytes in and out of the `usb_console` ring buffers.
     loop {
-        if successful_read {
-            blink_signals(&mut led_pin, &mut delay, &BLINK_OK_SHORT_SHORT_LONG);
-        } else {
-            blink_signals(&mut led_pin, &mut delay, &BLINK_OK_SHORT_LONG);
+        if let Some(line) = usb_console::read_line() {
+            dispatch_command(&line, &mut dir);
         }
-
-        delay.delay_ms(1000);
     }
 }